@@ -3,14 +3,228 @@
     windows_subsystem = "windows"
 )]
 
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::ToSocketAddrs;
 use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
 use std::env;
+use serde::{Deserialize, Serialize};
+use tauri::api::process::{Command as SidecarCommand, CommandChild, CommandEvent};
+use tauri::async_runtime::Receiver;
 use tauri::Manager;
 
+// 被管理的后端进程：普通子进程 (全局 CLI / 内嵌资源) 或 Tauri sidecar，
+// sidecar 由运行时的 command API 跟踪，应用退出时会自动被杀掉
+enum ManagedBackend {
+    Native {
+        child: Child,
+        mode: &'static str,
+    },
+    Sidecar {
+        child: CommandChild,
+        exited: Arc<AtomicBool>,
+        rx: Option<Receiver<CommandEvent>>,
+    },
+}
+
+impl ManagedBackend {
+    fn pid(&self) -> u32 {
+        match self {
+            ManagedBackend::Native { child, .. } => child.id(),
+            ManagedBackend::Sidecar { child, .. } => child.pid(),
+        }
+    }
+
+    // 对应 get_backend_info 里展示的启动方式: "sidecar" / "global CLI" / "embedded"
+    fn mode_label(&self) -> &'static str {
+        match self {
+            ManagedBackend::Native { mode, .. } => mode,
+            ManagedBackend::Sidecar { .. } => "sidecar",
+        }
+    }
+
+    fn has_exited(&mut self) -> bool {
+        match self {
+            ManagedBackend::Native { child, .. } => matches!(child.try_wait(), Ok(Some(_))),
+            ManagedBackend::Sidecar { exited, .. } => exited.load(Ordering::SeqCst),
+        }
+    }
+}
+
 // 后端进程管理
-struct BackendProcess(Mutex<Option<Child>>);
+struct BackendProcess(Mutex<Option<ManagedBackend>>);
+
+// 标记当前停止是否为用户主动发起 (重启/关闭窗口)，supervisor 线程据此区分崩溃与主动停止
+struct ShutdownFlag(AtomicBool);
+
+impl ShutdownFlag {
+    fn new() -> Self {
+        ShutdownFlag(AtomicBool::new(false))
+    }
+
+    fn begin(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn end(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    fn is_intentional(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// 后端日志环形缓冲区，容量上限，超出后丢弃最旧的行
+const LOG_BUFFER_CAPACITY: usize = 2000;
+
+struct BackendLogs(Mutex<VecDeque<String>>);
+
+impl BackendLogs {
+    fn new() -> Self {
+        BackendLogs(Mutex::new(VecDeque::with_capacity(LOG_BUFFER_CAPACITY)))
+    }
+
+    fn push(&self, line: String) {
+        if let Ok(mut buf) = self.0.lock() {
+            if buf.len() >= LOG_BUFFER_CAPACITY {
+                buf.pop_front();
+            }
+            buf.push_back(line);
+        }
+    }
+
+    fn tail(&self, limit: usize) -> Vec<String> {
+        match self.0.lock() {
+            Ok(buf) => {
+                let skip = buf.len().saturating_sub(limit);
+                buf.iter().skip(skip).cloned().collect()
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+// 推送到前端的日志行
+#[derive(Clone, Serialize)]
+struct LogLine {
+    stream: &'static str,
+    line: String,
+    ts: u128,
+}
+
+fn now_ts_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+// 后端运行配置：可通过 wqbot.toml 及环境变量覆盖，避免重新编译就能换端口、换 Node 路径
+#[derive(Clone, Serialize, Deserialize)]
+struct BackendConfig {
+    host: String,
+    port: u16,
+    node_path: Option<PathBuf>,
+    backend_entry: Option<PathBuf>,
+    #[serde(default)]
+    extra_args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            host: BACKEND_HOST.to_string(),
+            port: BACKEND_PORT,
+            node_path: None,
+            backend_entry: None,
+            extra_args: Vec::new(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+// 持有配置的锁即便中毒，里面的数据通常仍然完好，恢复使用而不是让调用方 panic，
+// 否则任意一次持锁期间的 panic 会让后续所有配置访问（包括长期存活的 watchdog 线程）连带崩溃
+fn lock_config(config: &Mutex<BackendConfig>) -> std::sync::MutexGuard<'_, BackendConfig> {
+    config.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+const CONFIG_FILE_NAME: &str = "wqbot.toml";
+
+fn config_file_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_config_dir()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+// 从配置文件加载 BackendConfig，再叠加环境变量覆盖
+fn load_config(app_handle: &tauri::AppHandle) -> BackendConfig {
+    let mut config = config_file_path(app_handle)
+        .and_then(|path| std::fs::read_to_string(&path).ok().map(|text| (path, text)))
+        .and_then(|(path, text)| {
+            let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => serde_json::from_str::<BackendConfig>(&text).ok(),
+                _ => toml::from_str::<BackendConfig>(&text).ok(),
+            };
+            if parsed.is_none() {
+                println!("配置文件解析失败，使用默认配置: {:?}", path);
+            }
+            parsed
+        })
+        .unwrap_or_default();
+
+    if let Ok(host) = env::var("WQBOT_HOST") {
+        config.host = host;
+    }
+    if let Ok(port) = env::var("WQBOT_PORT") {
+        if let Ok(port) = port.parse() {
+            config.port = port;
+        }
+    }
+    if let Ok(node_path) = env::var("WQBOT_NODE_PATH") {
+        config.node_path = Some(PathBuf::from(node_path));
+    }
+    if let Ok(backend_entry) = env::var("WQBOT_BACKEND_ENTRY") {
+        config.backend_entry = Some(PathBuf::from(backend_entry));
+    }
+
+    config
+}
+
+// 将当前配置写回配置文件，供 set_config 持久化用户的修改
+fn save_config(app_handle: &tauri::AppHandle, config: &BackendConfig) -> Result<(), String> {
+    let path = config_file_path(app_handle).ok_or("无法定位应用配置目录")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let text = toml::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+// 获取当前后端配置
+#[tauri::command]
+fn get_config(config: tauri::State<Arc<Mutex<BackendConfig>>>) -> BackendConfig {
+    lock_config(&config).clone()
+}
+
+// 更新并持久化后端配置
+#[tauri::command]
+fn set_config(
+    app_handle: tauri::AppHandle,
+    new_config: BackendConfig,
+    config: tauri::State<Arc<Mutex<BackendConfig>>>,
+) -> Result<(), String> {
+    save_config(&app_handle, &new_config)?;
+    *lock_config(&config) = new_config;
+    Ok(())
+}
 
 // 获取资源目录
 fn get_resource_dir() -> Option<PathBuf> {
@@ -94,9 +308,70 @@ fn find_node() -> Option<String> {
 }
 
 // 启动后端服务
-fn start_backend() -> Option<Child> {
+// 方式 0: 尝试以 Tauri sidecar 启动 (需要在 tauri.conf.json 的 tauri.bundle.externalBin
+// 中声明 "node" 并随安装包分发)。sidecar 由运行时跟踪，应用退出时自动被杀掉，
+// 不再依赖用户机器上安装的 Node
+fn try_start_sidecar(config: &BackendConfig) -> Option<ManagedBackend> {
+    let sidecar = match SidecarCommand::new_sidecar("node") {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            println!("未找到已打包的 sidecar，跳过 sidecar 启动方式: {}", e);
+            return None;
+        }
+    };
+
+    // sidecar 进程默认沿用应用启动时的工作目录，相对路径的入口文件必须锚定到
+    // 资源目录，否则 node 会因为找不到模块而启动后立刻退出
+    let resource_dir = get_resource_dir();
+    let entry = config.backend_entry.clone().unwrap_or_else(|| {
+        resource_dir
+            .clone()
+            .map(|dir| {
+                dir.join("packages")
+                    .join("backend")
+                    .join("dist")
+                    .join("index.js")
+            })
+            .unwrap_or_else(|| PathBuf::from("backend/index.js"))
+    });
+
+    let mut args = vec![entry.to_string_lossy().to_string()];
+    args.extend(config.extra_args.iter().cloned());
+
+    let mut sidecar = sidecar
+        .args(args)
+        .env("HOST", &config.host)
+        .env("PORT", config.port.to_string());
+    for (key, value) in &config.env {
+        sidecar = sidecar.env(key, value);
+    }
+    if let Some(resource_dir) = &resource_dir {
+        sidecar = sidecar.current_dir(resource_dir.clone());
+    }
+
+    match sidecar.spawn() {
+        Ok((rx, child)) => {
+            println!("后端服务已启动 (sidecar, PID: {})", child.pid());
+            Some(ManagedBackend::Sidecar {
+                child,
+                exited: Arc::new(AtomicBool::new(false)),
+                rx: Some(rx),
+            })
+        }
+        Err(e) => {
+            println!("sidecar 启动失败: {}", e);
+            None
+        }
+    }
+}
+
+fn start_backend(config: &BackendConfig) -> Option<ManagedBackend> {
     println!("正在启动后端服务...");
 
+    if let Some(backend) = try_start_sidecar(config) {
+        return Some(backend);
+    }
+
     // 方式 1: 尝试使用全局安装的 wqbot CLI
     let global_commands = if cfg!(target_os = "windows") {
         vec![
@@ -113,13 +388,20 @@ fn start_backend() -> Option<Child> {
     for (cmd, args) in global_commands {
         match Command::new(cmd)
             .args(&args)
+            .args(&config.extra_args)
+            .env("HOST", &config.host)
+            .env("PORT", config.port.to_string())
+            .envs(&config.env)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
         {
             Ok(child) => {
                 println!("后端服务已启动 (全局 CLI, PID: {})", child.id());
-                return Some(child);
+                return Some(ManagedBackend::Native {
+                    child,
+                    mode: "global CLI",
+                });
             }
             Err(e) => {
                 println!("尝试全局 CLI 失败: {}", e);
@@ -131,17 +413,29 @@ fn start_backend() -> Option<Child> {
     if let Some(resource_dir) = get_resource_dir() {
         println!("尝试使用内嵌资源: {:?}", resource_dir);
 
-        if let Some(node) = find_node() {
-            // 查找后端入口文件
-            let backend_entry = resource_dir
-                .join("packages")
-                .join("backend")
-                .join("dist")
-                .join("index.js");
+        let node = config
+            .node_path
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string())
+            .or_else(find_node);
+
+        if let Some(node) = node {
+            // 查找后端入口文件，可通过配置覆盖默认相对路径
+            let backend_entry = config.backend_entry.clone().unwrap_or_else(|| {
+                resource_dir
+                    .join("packages")
+                    .join("backend")
+                    .join("dist")
+                    .join("index.js")
+            });
 
             if backend_entry.exists() {
                 match Command::new(&node)
                     .arg(&backend_entry)
+                    .args(&config.extra_args)
+                    .env("HOST", &config.host)
+                    .env("PORT", config.port.to_string())
+                    .envs(&config.env)
                     .current_dir(&resource_dir)
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -149,7 +443,10 @@ fn start_backend() -> Option<Child> {
                 {
                     Ok(child) => {
                         println!("后端服务已启动 (内嵌资源, PID: {})", child.id());
-                        return Some(child);
+                        return Some(ManagedBackend::Native {
+                            child,
+                            mode: "embedded",
+                        });
                     }
                     Err(e) => {
                         println!("启动内嵌后端失败: {}", e);
@@ -168,67 +465,481 @@ fn start_backend() -> Option<Child> {
     None
 }
 
-// 停止后端服务
-fn stop_backend(process: &Mutex<Option<Child>>) {
-    if let Ok(mut guard) = process.lock() {
-        if let Some(mut child) = guard.take() {
-            println!("正在停止后端服务 (PID: {})...", child.id());
+// 将后端进程的 stdout/stderr 接入环形缓冲区并转发给前端，避免管道缓冲区写满导致子进程阻塞；
+// 原生子进程用 BufReader 轮询管道，sidecar 则消费其 CommandEvent 通道
+fn spawn_log_readers(app_handle: tauri::AppHandle, backend: &mut ManagedBackend, logs: Arc<BackendLogs>) {
+    match backend {
+        ManagedBackend::Native { child, .. } => {
+            if let Some(stdout) = child.stdout.take() {
+                let logs = logs.clone();
+                let app_handle = app_handle.clone();
+                std::thread::spawn(move || {
+                    for line in BufReader::new(stdout).lines().flatten() {
+                        logs.push(line.clone());
+                        let _ = app_handle.emit_all(
+                            "backend-log",
+                            LogLine {
+                                stream: "stdout",
+                                line,
+                                ts: now_ts_millis(),
+                            },
+                        );
+                    }
+                });
+            }
 
-            // 尝试优雅关闭
-            #[cfg(target_os = "windows")]
-            {
-                let _ = Command::new("taskkill")
-                    .args(["/PID", &child.id().to_string(), "/T"])
-                    .output();
+            if let Some(stderr) = child.stderr.take() {
+                std::thread::spawn(move || {
+                    for line in BufReader::new(stderr).lines().flatten() {
+                        logs.push(line.clone());
+                        let _ = app_handle.emit_all(
+                            "backend-log",
+                            LogLine {
+                                stream: "stderr",
+                                line,
+                                ts: now_ts_millis(),
+                            },
+                        );
+                    }
+                });
             }
+        }
+        ManagedBackend::Sidecar { rx, exited, .. } => {
+            if let Some(mut rx) = rx.take() {
+                let exited = exited.clone();
+                std::thread::spawn(move || {
+                    while let Some(event) = tauri::async_runtime::block_on(rx.recv()) {
+                        match event {
+                            CommandEvent::Stdout(line) => {
+                                logs.push(line.clone());
+                                let _ = app_handle.emit_all(
+                                    "backend-log",
+                                    LogLine {
+                                        stream: "stdout",
+                                        line,
+                                        ts: now_ts_millis(),
+                                    },
+                                );
+                            }
+                            CommandEvent::Stderr(line) => {
+                                logs.push(line.clone());
+                                let _ = app_handle.emit_all(
+                                    "backend-log",
+                                    LogLine {
+                                        stream: "stderr",
+                                        line,
+                                        ts: now_ts_millis(),
+                                    },
+                                );
+                            }
+                            CommandEvent::Error(err) => {
+                                println!("sidecar 报告错误: {}", err);
+                            }
+                            CommandEvent::Terminated(_) => {
+                                exited.store(true, Ordering::SeqCst);
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    exited.store(true, Ordering::SeqCst);
+                });
+            }
+        }
+    }
+}
+
+// 优雅关闭的等待时限，超时后升级为强制终止
+const GRACEFUL_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+const GRACEFUL_SHUTDOWN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// 停止后端服务：先礼后兵，优雅关闭超时后强制终止，避免无限期阻塞窗口关闭
+fn stop_backend(process: &Mutex<Option<ManagedBackend>>) {
+    // 先取出被管理的后端并释放锁，避免优雅等待/强制终止这最长数秒的阻塞操作
+    // 占着锁，导致 watchdog 轮询和其他状态访问被一并卡住
+    let backend = match process.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(_) => None,
+    };
+
+    if let Some(backend) = backend {
+        match backend {
+            ManagedBackend::Native { mut child, mode } => {
+                let pid = child.id();
+                println!("正在停止后端服务 ({}, PID: {})...", mode, pid);
+
+                // 尝试优雅关闭
+                #[cfg(target_os = "windows")]
+                {
+                    let _ = Command::new("taskkill")
+                        .args(["/PID", &pid.to_string(), "/T"])
+                        .output();
+                }
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = Command::new("kill")
+                        .args(["-TERM", &pid.to_string()])
+                        .output();
+                }
 
-            #[cfg(not(target_os = "windows"))]
-            {
-                let _ = Command::new("kill")
-                    .args(["-TERM", &child.id().to_string()])
-                    .output();
+                // 在宽限期内轮询，等待进程自行退出
+                let deadline = std::time::Instant::now() + GRACEFUL_SHUTDOWN_TIMEOUT;
+                let exited = loop {
+                    match child.try_wait() {
+                        Ok(Some(_)) => break true,
+                        Ok(None) => {
+                            if std::time::Instant::now() >= deadline {
+                                break false;
+                            }
+                            std::thread::sleep(GRACEFUL_SHUTDOWN_POLL_INTERVAL);
+                        }
+                        Err(_) => break false,
+                    }
+                };
+
+                if exited {
+                    println!("后端服务已优雅停止 ({}, PID: {})", mode, pid);
+                } else {
+                    println!(
+                        "后端服务未在 {:?} 内退出，强制终止 ({}, PID: {})",
+                        GRACEFUL_SHUTDOWN_TIMEOUT, mode, pid
+                    );
+
+                    #[cfg(target_os = "windows")]
+                    {
+                        let _ = Command::new("taskkill")
+                            .args(["/PID", &pid.to_string(), "/T", "/F"])
+                            .output();
+                    }
+
+                    #[cfg(not(target_os = "windows"))]
+                    {
+                        let _ = Command::new("kill")
+                            .args(["-KILL", &pid.to_string()])
+                            .output();
+                    }
+
+                    let _ = child.wait();
+                    println!("后端服务已强制停止 ({}, PID: {})", mode, pid);
+                }
             }
+            ManagedBackend::Sidecar { child, exited, .. } => {
+                let pid = child.pid();
+                println!("正在停止后端服务 (sidecar, PID: {})...", pid);
+
+                // sidecar 没有跨平台的"发送 SIGTERM"接口，复用 PID 直接发送，
+                // 退出状态通过读取线程观察到的 CommandEvent::Terminated 上报
+                #[cfg(target_os = "windows")]
+                {
+                    let _ = Command::new("taskkill")
+                        .args(["/PID", &pid.to_string(), "/T"])
+                        .output();
+                }
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = Command::new("kill")
+                        .args(["-TERM", &pid.to_string()])
+                        .output();
+                }
 
-            // 等待进程结束
-            let _ = child.wait();
-            println!("后端服务已停止");
+                let deadline = std::time::Instant::now() + GRACEFUL_SHUTDOWN_TIMEOUT;
+                let exited_gracefully = loop {
+                    if exited.load(Ordering::SeqCst) {
+                        break true;
+                    }
+                    if std::time::Instant::now() >= deadline {
+                        break false;
+                    }
+                    std::thread::sleep(GRACEFUL_SHUTDOWN_POLL_INTERVAL);
+                };
+
+                if exited_gracefully {
+                    println!("后端服务已优雅停止 (sidecar, PID: {})", pid);
+                } else {
+                    println!(
+                        "后端服务未在 {:?} 内退出，强制终止 (sidecar, PID: {})",
+                        GRACEFUL_SHUTDOWN_TIMEOUT, pid
+                    );
+                    let _ = child.kill();
+                    println!("后端服务已强制停止 (sidecar, PID: {})", pid);
+                }
+            }
         }
     }
 }
 
-// 检查后端是否运行
+const BACKEND_HOST: &str = "127.0.0.1";
+const BACKEND_PORT: u16 = 3721;
+const HEALTH_CHECK_PATH: &str = "/health";
+const HEALTH_CHECK_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+const HEALTH_CHECK_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+// 对 host:port/path 发起一次 HTTP 健康检查，只有 2xx 状态码才算就绪；
+// 如果连接成功但响应无法解析为 HTTP，退化为"端口已连通"判定
+fn probe_http_health(host: &str, port: u16, path: &str) -> bool {
+    let addr = match format!("{}:{}", host, port).to_socket_addrs() {
+        Ok(mut addrs) => match addrs.next() {
+            Some(addr) => addr,
+            None => return false,
+        },
+        Err(_) => return false,
+    };
+
+    let mut stream = match std::net::TcpStream::connect_timeout(&addr, HEALTH_CHECK_CONNECT_TIMEOUT) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+
+    let _ = stream.set_read_timeout(Some(HEALTH_CHECK_READ_TIMEOUT));
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
+    }
+
+    let mut response = String::new();
+    if stream.read_to_string(&mut response).is_err() || response.is_empty() {
+        // 读取超时或对端未写任何内容，说明后端尚未就绪，不能视为健康
+        return false;
+    }
+
+    match response.lines().next() {
+        Some(status_line) => status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .map(|code| (200..300).contains(&code))
+            .unwrap_or(true),
+        None => true,
+    }
+}
+
+// 检查后端是否运行：HTTP 健康检查优先，解析失败时退化为裸 TCP 连接判定
+#[tauri::command]
+fn check_backend_status(config: tauri::State<Arc<Mutex<BackendConfig>>>) -> bool {
+    let config = lock_config(&config);
+    probe_http_health(&config.host, config.port, HEALTH_CHECK_PATH)
+}
+
+// 阻塞等待后端就绪，带退避地轮询 check_backend_status，超时后返回 false
 #[tauri::command]
-fn check_backend_status() -> bool {
-    match std::net::TcpStream::connect("127.0.0.1:3721") {
-        Ok(_) => true,
-        Err(_) => false,
+fn wait_for_backend_ready(timeout_ms: u64, config: tauri::State<Arc<Mutex<BackendConfig>>>) -> bool {
+    let (host, port) = {
+        let config = lock_config(&config);
+        (config.host.clone(), config.port)
+    };
+    wait_for_backend_ready_at(&host, port, timeout_ms)
+}
+
+// wait_for_backend_ready 的非 command 版本，供 setup 中的就绪等待线程和 restart_backend 等已持有 host/port 的调用方复用
+fn wait_for_backend_ready_at(host: &str, port: u16, timeout_ms: u64) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(timeout_ms);
+    let mut backoff = std::time::Duration::from_millis(100);
+    let max_backoff = std::time::Duration::from_millis(500);
+
+    loop {
+        if probe_http_health(host, port, HEALTH_CHECK_PATH) {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(backoff);
+        backoff = std::cmp::min(backoff * 2, max_backoff);
     }
 }
 
 // 重启后端
 #[tauri::command]
-fn restart_backend(state: tauri::State<BackendProcess>) -> bool {
+fn restart_backend(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<BackendProcess>,
+    logs: tauri::State<Arc<BackendLogs>>,
+    shutdown_flag: tauri::State<Arc<ShutdownFlag>>,
+    config: tauri::State<Arc<Mutex<BackendConfig>>>,
+) -> bool {
+    // 标记为主动停止，避免 supervisor 线程把这次退出当成崩溃来处理
+    shutdown_flag.begin();
+    // stop_backend 内部已带超时等待子进程真正退出，端口此时已释放
     stop_backend(&state.0);
 
-    // 等待端口释放
-    std::thread::sleep(std::time::Duration::from_millis(500));
-
-    if let Some(child) = start_backend() {
+    let config = lock_config(&config).clone();
+    let result = if let Some(mut backend) = start_backend(&config) {
+        spawn_log_readers(app_handle, &mut backend, logs.inner().clone());
         if let Ok(mut guard) = state.0.lock() {
-            *guard = Some(child);
-            return true;
+            *guard = Some(backend);
         }
+        true
+    } else {
+        false
+    };
+
+    // 新进程已装入受管状态，立即结束"主动停止"标记，后续的就绪等待期间
+    // watchdog 仍需能正常捕获崩溃，不应被当成本次重启的一部分而忽略
+    shutdown_flag.end();
+
+    if !result {
+        return false;
     }
-    false
+
+    // 等待新进程真正就绪，而不是假定启动即可用
+    wait_for_backend_ready_at(&config.host, config.port, 10_000)
+}
+
+// 崩溃恢复的重试上限，超过后放弃自动重启
+const WATCHDOG_MAX_RETRIES: u32 = 10;
+// 退避的起始与上限间隔
+const WATCHDOG_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+const WATCHDOG_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+// 进程存活超过该时长视为"稳定运行"，重置退避计数
+const WATCHDOG_STABLE_UPTIME: std::time::Duration = std::time::Duration::from_secs(60);
+// supervisor 轮询子进程状态的间隔
+const WATCHDOG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Clone, Serialize)]
+struct BackendCrashedPayload {
+    retries: u32,
+}
+
+// 崩溃恢复 watchdog：定期检查被管理的后端进程是否意外退出，按指数退避自动重启，
+// 超过重试上限后放弃并通知前端
+fn spawn_watchdog(
+    app_handle: tauri::AppHandle,
+    logs: Arc<BackendLogs>,
+    shutdown_flag: Arc<ShutdownFlag>,
+    config: Arc<Mutex<BackendConfig>>,
+) {
+    std::thread::spawn(move || {
+        let mut backoff = WATCHDOG_INITIAL_BACKOFF;
+        let mut retries: u32 = 0;
+        // 达到重试上限后放弃自动重启，但线程本身继续存活，
+        // 等待后端被重新拉起（例如用户手动调用 restart_backend）后恢复监控
+        let mut gave_up = false;
+
+        loop {
+            std::thread::sleep(WATCHDOG_POLL_INTERVAL);
+
+            let state = app_handle.state::<BackendProcess>();
+            let running = match state.0.lock() {
+                Ok(mut guard) => guard.as_mut().map(|backend| !backend.has_exited()),
+                Err(_) => None,
+            };
+
+            if gave_up {
+                if running == Some(true) {
+                    println!("检测到后端服务已重新运行，恢复崩溃监控");
+                    gave_up = false;
+                    backoff = WATCHDOG_INITIAL_BACKOFF;
+                    retries = 0;
+                }
+                continue;
+            }
+
+            let exited = match running {
+                Some(alive) => !alive,
+                None => false,
+            };
+
+            if !exited {
+                continue;
+            }
+
+            if shutdown_flag.is_intentional() {
+                // 用户主动重启/关闭，不计入崩溃
+                continue;
+            }
+
+            if let Ok(mut guard) = state.0.lock() {
+                guard.take();
+            }
+
+            if retries >= WATCHDOG_MAX_RETRIES {
+                println!("后端服务崩溃次数已达上限 ({})，暂停自动重启，等待人工介入", WATCHDOG_MAX_RETRIES);
+                let _ = app_handle.emit_all("backend-crashed", BackendCrashedPayload { retries });
+                gave_up = true;
+                continue;
+            }
+
+            retries += 1;
+            println!(
+                "检测到后端服务意外退出，{:?} 后进行第 {} 次重启尝试",
+                backoff, retries
+            );
+            std::thread::sleep(backoff);
+
+            let started_at = std::time::Instant::now();
+            let backend_config = lock_config(&config).clone();
+            if let Some(mut backend) = start_backend(&backend_config) {
+                spawn_log_readers(app_handle.clone(), &mut backend, logs.clone());
+                if let Ok(mut guard) = state.0.lock() {
+                    *guard = Some(backend);
+                }
+
+                // 在稳定窗口期内持续轮询，新进程若能撑过这段时间则重置退避和重试计数
+                let stable_deadline = started_at + WATCHDOG_STABLE_UPTIME;
+                let mut crashed_again = false;
+                while std::time::Instant::now() < stable_deadline {
+                    std::thread::sleep(WATCHDOG_POLL_INTERVAL);
+                    if shutdown_flag.is_intentional() {
+                        break;
+                    }
+                    let exited_again = match state.0.lock() {
+                        Ok(mut guard) => match guard.as_mut() {
+                            Some(backend) => backend.has_exited(),
+                            None => true,
+                        },
+                        Err(_) => false,
+                    };
+                    if exited_again {
+                        crashed_again = true;
+                        break;
+                    }
+                }
+
+                if !crashed_again {
+                    backoff = WATCHDOG_INITIAL_BACKOFF;
+                    retries = 0;
+                    continue;
+                }
+            }
+
+            backoff = std::cmp::min(backoff * 2, WATCHDOG_MAX_BACKOFF);
+        }
+    });
+}
+
+// 获取后端日志尾部
+#[tauri::command]
+fn get_backend_logs(limit: usize, logs: tauri::State<Arc<BackendLogs>>) -> Vec<String> {
+    logs.tail(limit)
 }
 
 // 获取后端日志
 #[tauri::command]
-fn get_backend_info() -> String {
+fn get_backend_info(
+    config: tauri::State<Arc<Mutex<BackendConfig>>>,
+    process: tauri::State<BackendProcess>,
+) -> String {
+    let config = lock_config(&config).clone();
     let mut info = String::new();
 
     info.push_str(&format!("平台: {}\n", std::env::consts::OS));
     info.push_str(&format!("架构: {}\n", std::env::consts::ARCH));
+    info.push_str(&format!("后端地址: {}:{}\n", config.host, config.port));
+
+    let launch_mode = match process.0.lock() {
+        Ok(guard) => guard.as_ref().map(|backend| backend.mode_label()),
+        Err(_) => None,
+    };
+    info.push_str(&format!(
+        "启动方式: {}\n",
+        launch_mode.unwrap_or("未启动")
+    ));
 
     if let Some(resource_dir) = get_resource_dir() {
         info.push_str(&format!("资源目录: {:?}\n", resource_dir));
@@ -236,35 +947,198 @@ fn get_backend_info() -> String {
         info.push_str("资源目录: 未找到\n");
     }
 
-    if let Some(node) = find_node() {
+    let node = config
+        .node_path
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .or_else(find_node);
+    if let Some(node) = node {
         info.push_str(&format!("Node.js: {}\n", node));
     } else {
         info.push_str("Node.js: 未找到\n");
     }
 
-    let status = if check_backend_status() { "运行中" } else { "未运行" };
+    let status = if probe_http_health(&config.host, config.port, HEALTH_CHECK_PATH) {
+        "运行中"
+    } else {
+        "未运行"
+    };
     info.push_str(&format!("后端状态: {}\n", status));
 
     info
 }
 
+// 单实例锁文件名，内容为持有者 PID
+const INSTANCE_LOCK_FILE_NAME: &str = "wqbot.lock";
+// 第二实例用于唤醒已运行实例窗口的本地控制端口
+const FOCUS_SIGNAL_PORT: u16 = 37211;
+
+fn instance_lock_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(INSTANCE_LOCK_FILE_NAME))
+}
+
+// 判断指定 PID 的进程是否仍然存活，用于识别陈旧的锁文件
+fn is_process_alive(pid: u32) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid)])
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+}
+
+// 尝试获取单实例锁：用原子创建代替"先检查再写入"，避免两个进程前后脚启动时
+// 都读到锁不存在从而同时获取成功；锁文件已存在时只有持有者确认已死才清理重试
+const LOCK_ACQUIRE_MAX_ATTEMPTS: u32 = 5;
+
+fn acquire_single_instance_lock(path: &std::path::Path) -> bool {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    for _ in 0..LOCK_ACQUIRE_MAX_ATTEMPTS {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(mut file) => {
+                return file
+                    .write_all(std::process::id().to_string().as_bytes())
+                    .is_ok();
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let holder_alive = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|existing| existing.trim().parse::<u32>().ok())
+                    .map(is_process_alive)
+                    .unwrap_or(false);
+
+                if holder_alive {
+                    return false;
+                }
+
+                // 锁文件属于已经退出的旧实例，清理后重试一次原子创建
+                if std::fs::remove_file(path).is_err() {
+                    return false;
+                }
+            }
+            Err(_) => return false,
+        }
+    }
+
+    false
+}
+
+// 通知已运行的实例把窗口带到前台
+fn request_existing_instance_focus() {
+    if let Ok(mut stream) = std::net::TcpStream::connect(("127.0.0.1", FOCUS_SIGNAL_PORT)) {
+        let _ = stream.write_all(b"focus\n");
+    }
+}
+
+// 监听唤醒信号，收到后聚焦主窗口；端口由 OS 仲裁，随本实例退出自动释放
+fn spawn_focus_listener(app_handle: tauri::AppHandle) {
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", FOCUS_SIGNAL_PORT)) {
+        Ok(listener) => listener,
+        Err(_) => return,
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            drop(stream);
+            if let Some(window) = app_handle.get_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+    });
+}
+
 fn main() {
-    // 启动后端服务
-    let backend_child = start_backend();
+    let logs = Arc::new(BackendLogs::new());
+    let shutdown_flag = Arc::new(ShutdownFlag::new());
 
     tauri::Builder::default()
-        .manage(BackendProcess(Mutex::new(backend_child)))
+        .manage(BackendProcess(Mutex::new(None)))
+        .manage(logs.clone())
+        .manage(shutdown_flag.clone())
         .invoke_handler(tauri::generate_handler![
             check_backend_status,
+            wait_for_backend_ready,
             restart_backend,
-            get_backend_info
+            get_backend_info,
+            get_backend_logs,
+            get_config,
+            set_config
         ])
+        .setup(move |app| {
+            // 单实例守卫：已有实例在运行时，唤醒它的窗口并退出，不再启动后端
+            if let Some(lock_path) = instance_lock_path(&app.handle()) {
+                if !acquire_single_instance_lock(&lock_path) {
+                    println!("检测到已有实例在运行，唤醒已有窗口并退出");
+                    request_existing_instance_focus();
+                    app.handle().exit(0);
+                    return Ok(());
+                }
+            }
+            spawn_focus_listener(app.handle());
+
+            // 加载配置文件 (叠加环境变量覆盖)，端口/Node 路径等不再需要重新编译
+            let config = Arc::new(Mutex::new(load_config(&app.handle())));
+            app.manage(config.clone());
+
+            // 启动后端服务，并将其 stdout/stderr 接入日志子系统
+            let backend_config = lock_config(&config).clone();
+            if let Some(mut backend) = start_backend(&backend_config) {
+                spawn_log_readers(app.handle(), &mut backend, logs.clone());
+                let state = app.state::<BackendProcess>();
+                if let Ok(mut guard) = state.0.lock() {
+                    *guard = Some(backend);
+                }
+
+                // 后台等待后端就绪，而不是假定端口绑定即可用，就绪/超时均通知前端
+                let ready_handle = app.handle();
+                std::thread::spawn(move || {
+                    let ready =
+                        wait_for_backend_ready_at(&backend_config.host, backend_config.port, 30_000);
+                    let _ = ready_handle.emit_all("backend-ready", ready);
+                });
+            }
+
+            // 崩溃恢复 watchdog：后端意外退出时自动重启
+            spawn_watchdog(app.handle(), logs.clone(), shutdown_flag.clone(), config.clone());
+
+            Ok(())
+        })
         .on_window_event(|event| {
-            // 窗口关闭时停止后端
+            // 窗口关闭时停止后端，并清理单实例锁
             if let tauri::WindowEvent::Destroyed = event.event() {
                 if let Some(state) = event.window().try_state::<BackendProcess>() {
+                    if let Some(shutdown_flag) = event.window().try_state::<Arc<ShutdownFlag>>() {
+                        shutdown_flag.begin();
+                    }
                     stop_backend(&state.0);
                 }
+                if let Some(lock_path) = instance_lock_path(&event.window().app_handle()) {
+                    let _ = std::fs::remove_file(lock_path);
+                }
             }
         })
         .run(tauri::generate_context!())